@@ -4,6 +4,58 @@
 use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::io::{self, Write};
+use std::ops::Range;
+
+use chumsky::prelude::*;
+
+use ariadne::{Color, Label, Report, ReportKind, Source};
+use pulldown_cmark::{html as md_html, Parser as MarkdownParser};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tree_sitter::{Parser as TsParser, Query, QueryCursor};
+
+/// Sidecar file mapping each cell id to the hash (and captured output) it had
+/// the last time it was executed, so unchanged cells can be skipped.
+const CACHE_FILE: &str = ".litpro-cache.json";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct CellCacheEntry {
+    hash: String,
+    output: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct LitProCache {
+    cells: HashMap<String, CellCacheEntry>,
+}
+
+impl LitProCache {
+    fn load() -> Self {
+        std::fs::read_to_string(CACHE_FILE)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self) -> Result<(), LitProError> {
+        let contents = serde_json::to_string_pretty(self)
+            .map_err(|e| LitProError::IoError(io::Error::other(e)))?;
+        std::fs::write(CACHE_FILE, contents).map_err(LitProError::IoError)
+    }
+}
+
+/// Highlight query selecting the handful of node kinds we style: keywords,
+/// strings, comments, function names, and types.
+const RUST_HIGHLIGHT_QUERY: &str = r#"
+(line_comment) @comment
+(block_comment) @comment
+(string_literal) @string
+(function_item name: (identifier) @function)
+(call_expression function: (identifier) @function)
+(type_identifier) @type
+(primitive_type) @type
+["fn" "let" "if" "else" "match" "for" "while" "loop" "return" "struct" "enum" "impl" "pub" "use" "mod" "const" "mut"] @keyword
+"#;
 
 #[derive(Debug)]
 pub struct Cell {
@@ -11,17 +63,201 @@ pub struct Cell {
     pub code: String,
     pub dependencies: Vec<String>,
     pub executed: bool,
+    /// Info string from the opening fence, e.g. `rust` in ` ```rust `. Empty if omitted.
+    pub lang: String,
+    /// Byte range of this cell (marker through closing fence) in the source document.
+    pub span: Range<usize>,
+    /// Noweb-style `<<name>>` chunk references found in this cell's code. Unlike
+    /// `dependencies`, these don't order execution — they get textually expanded
+    /// during tangling.
+    pub references: Vec<String>,
 }
 
 impl Cell {
-    pub fn new(id: String, code: String, dependencies: Vec<String>) -> Self {
+    pub fn new(
+        id: String,
+        code: String,
+        dependencies: Vec<String>,
+        lang: String,
+        span: Range<usize>,
+        references: Vec<String>,
+    ) -> Self {
         Cell {
             id,
             code,
             dependencies,
             executed: false,
+            lang,
+            span,
+            references,
+        }
+    }
+}
+
+/// A single cell as produced by the grammar, before being indexed by id.
+struct ParsedCell {
+    id: String,
+    dependencies: Vec<String>,
+    lang: String,
+    code: String,
+    span: Range<usize>,
+    references: Vec<String>,
+}
+
+/// Extracts `<<name>>` noweb-style chunk references from a cell's code.
+fn extract_references(code: &str) -> Vec<String> {
+    let mut references = Vec::new();
+    let mut rest = code;
+    while let Some(start) = rest.find("<<") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find(">>") else {
+            break;
+        };
+        let name = after[..end].trim();
+        if !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            references.push(name.to_string());
+        }
+        rest = &after[end + 2..];
+    }
+    references
+}
+
+/// Parses a `depends:a,b,c` clause inside a cell marker's parentheses.
+fn depends_clause() -> impl Parser<char, Vec<String>, Error = Simple<char>> {
+    just("depends:")
+        .ignore_then(
+            text::ident()
+                .separated_by(just(','))
+                .at_least(1),
+        )
+}
+
+/// Parses `<!-- cell:ID (depends:a,b)? -->`, capturing id + dependencies.
+fn cell_marker() -> impl Parser<char, (String, Vec<String>), Error = Simple<char>> {
+    just("<!-- cell:")
+        .ignore_then(text::ident())
+        .then(
+            just(' ')
+                .repeated()
+                .ignore_then(
+                    depends_clause()
+                        .delimited_by(just('('), just(')'))
+                        .or_not(),
+                ),
+        )
+        .then_ignore(just(' ').repeated())
+        .then_ignore(just("-->"))
+        .map(|(id, deps)| (id, deps.unwrap_or_default()))
+}
+
+/// Parses a fenced code block with an arbitrary info string, e.g. ` ```rust `.
+fn code_fence() -> impl Parser<char, (String, String), Error = Simple<char>> {
+    just("```")
+        .ignore_then(none_of("\n").repeated().collect::<String>())
+        .then_ignore(text::newline())
+        .then(take_until(just('\n').or_not().ignore_then(just("```"))))
+        .map(|(lang, (code, _))| (lang.trim().to_string(), code.into_iter().collect::<String>()))
+}
+
+/// A whole cell: marker, optional blank/prose lines, then its fenced code body.
+fn cell() -> impl Parser<char, ParsedCell, Error = Simple<char>> {
+    cell_marker()
+        .then_ignore(take_until(just("```").rewind()).ignored())
+        .then(code_fence())
+        .map_with_span(|((id, dependencies), (lang, code)), span| {
+            let code = code.trim().to_string();
+            let references = extract_references(&code);
+            ParsedCell {
+                id,
+                dependencies,
+                lang,
+                code,
+                span,
+                references,
+            }
+        })
+}
+
+/// Chumsky's `Stream::from(&str)` enumerates `chars()`, so every span produced
+/// by `map_with_span`/parser errors is a *char* index, not a byte index. Everything
+/// downstream (`Cell::span`, diagnostics, the weave) treats spans as byte ranges
+/// into the original `&str`, so build a char-index -> byte-index table once per
+/// document and translate spans through it as soon as they leave the parser.
+fn char_byte_offsets(content: &str) -> Vec<usize> {
+    content
+        .char_indices()
+        .map(|(b, _)| b)
+        .chain(std::iter::once(content.len()))
+        .collect()
+}
+
+fn char_span_to_byte_span(offsets: &[usize], span: Range<usize>) -> Range<usize> {
+    offsets[span.start]..offsets[span.end]
+}
+
+/// Skips everything up to (but not including) the next cell marker, or to EOF.
+fn skip_to_next_cell() -> impl Parser<char, (), Error = Simple<char>> {
+    take_until(just("<!-- cell:").rewind().ignored().or(end()))
+        .ignored()
+}
+
+/// Top-level grammar: cells scattered through arbitrary prose. A malformed
+/// marker or fence is recovered from by skipping ahead to the next `<` and
+/// retrying there, so one bad cell doesn't swallow the rest of the document.
+fn document() -> impl Parser<char, Vec<ParsedCell>, Error = Simple<char>> {
+    skip_to_next_cell()
+        .ignore_then(cell().map(Some).recover_with(skip_until(['<'], |_| None).skip_start()))
+        .repeated()
+        .then_ignore(skip_to_next_cell())
+        .then_ignore(end())
+        .map(|cells| cells.into_iter().flatten().collect())
+}
+
+/// A single span-pointing parse/semantic problem, rendered via `ariadne`.
+struct Diagnostic {
+    span: Range<usize>,
+    message: String,
+    /// An optional secondary label, e.g. pointing at the cell a duplicate id collides with.
+    secondary: Option<(Range<usize>, String)>,
+}
+
+impl Diagnostic {
+    fn new(span: Range<usize>, message: impl Into<String>) -> Self {
+        Diagnostic {
+            span,
+            message: message.into(),
+            secondary: None,
         }
     }
+
+    fn with_secondary(mut self, span: Range<usize>, message: impl Into<String>) -> Self {
+        self.secondary = Some((span, message.into()));
+        self
+    }
+}
+
+/// Renders accumulated diagnostics as `ariadne` reports with carets under the
+/// offending span (and any secondary span), writing each to stderr.
+fn report_diagnostics(source: &str, diagnostics: &[Diagnostic]) {
+    for diag in diagnostics {
+        let mut builder = Report::build(ReportKind::Error, (), diag.span.start)
+            .with_message(&diag.message)
+            .with_label(
+                Label::new(diag.span.clone())
+                    .with_message(&diag.message)
+                    .with_color(Color::Red),
+            );
+
+        if let Some((span, message)) = &diag.secondary {
+            builder = builder.with_label(
+                Label::new(span.clone())
+                    .with_message(message)
+                    .with_color(Color::Blue),
+            );
+        }
+
+        let _ = builder.finish().eprint(Source::from(source));
+    }
 }
 
 #[derive(Debug)]
@@ -29,6 +265,9 @@ pub enum LitProError {
     IoError(std::io::Error),
     CircularDependencyError,
     ParseError(String),
+    /// `rustc` failed to compile the assembled program. `cell` is the cell the
+    /// offending line was mapped back to via its span in the generated source.
+    CompileError { cell: String, stderr: String },
 }
 
 impl fmt::Display for LitProError {
@@ -37,99 +276,164 @@ impl fmt::Display for LitProError {
             LitProError::IoError(e) => write!(f, "IO Error: {}", e),
             LitProError::CircularDependencyError => write!(f, "Circular dependency detected"),
             LitProError::ParseError(msg) => write!(f, "Parse Error: {}", msg),
+            LitProError::CompileError { cell, stderr } => {
+                write!(f, "Compile error in cell '{}':\n{}", cell, stderr)
+            }
         }
     }
 }
 
 impl std::error::Error for LitProError {}
 
+/// A block of the original document in reading order: prose to weave through a
+/// Markdown renderer, or a reference to a cell to splice in as rendered code.
+#[derive(Debug, Clone)]
+enum DocBlock {
+    Prose(String),
+    Cell(String),
+}
+
 pub struct LitPro {
     cells: HashMap<String, Cell>,
+    /// The document's prose and cells in reading order, for weaving into HTML.
+    document_order: Vec<DocBlock>,
+}
+
+impl Default for LitPro {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl LitPro {
     pub fn new() -> Self {
         LitPro {
             cells: HashMap::new(),
+            document_order: Vec::new(),
         }
     }
 
-    /// Parse cells from literate programming content
+    /// Parse cells from literate programming content using the `chumsky` grammar.
+    ///
+    /// Unlike a byte-offset scan, this understands nested/indented fences, arbitrary
+    /// info strings, and recovers past a malformed marker instead of losing the rest
+    /// of the document. Each produced `Cell` carries the byte span it was parsed from.
+    /// Problems (malformed markers, duplicate ids, unknown dependencies) are collected
+    /// as `Diagnostic`s and rendered as caret-accurate reports to stderr rather than
+    /// failing on the first one.
     pub fn parse_cells(&mut self, content: &str) -> Result<(), LitProError> {
-        // This is a simplified parser - in a real implementation, you'd want a more robust parser
-        let mut chars = content.chars().peekable();
-        let mut pos = 0;
+        let (cells, parse_errors) = document().parse_recovery(content);
+        let offsets = char_byte_offsets(content);
+        let mut cells = cells.unwrap_or_default();
+        for parsed in &mut cells {
+            parsed.span = char_span_to_byte_span(&offsets, parsed.span.clone());
+        }
 
-        while pos < content.len() {
-            if let Some(cell_start) = content[pos..].find("<!-- cell:") {
-                let cell_start_abs = pos + cell_start;
-                pos = cell_start_abs;
-
-                // Find the end of the cell marker
-                if let Some(marker_end) = content[pos..].find("-->") {
-                    let marker_end_abs = pos + marker_end + 3; // +3 for "-->"
-                    
-                    // Extract the cell marker content
-                    let marker = &content[pos + 11..marker_end_abs - 3]; // +11 for "<!-- cell:"
-                    
-                    // Parse cell ID and dependencies
-                    let parts: Vec<&str> = marker.split_whitespace().collect();
-                    if parts.is_empty() {
-                        continue;
-                    }
-                    
-                    let cell_id = parts[0].to_string();
-                    
-                    // Parse dependencies
-                    let mut dependencies = Vec::new();
-                    for part in parts.iter().skip(1) {
-                        if part.starts_with("depends:") {
-                            let deps_str = &part[8..]; // skip "depends:"
-                            dependencies.extend(deps_str.split(',').map(|s| s.trim().to_string()));
-                        }
-                    }
-                    
-                    // Find the code block
-                    let code_start_marker = &content[marker_end_abs..];
-                    if let Some(code_start) = code_start_marker.find("```rust") {
-                        let code_start_abs = marker_end_abs + code_start + 8; // +8 for "```rust"
-                        
-                        if let Some(code_end) = code_start_marker[code_start + 8..].find("```") {
-                            let code_end_abs = code_start_abs + code_end;
-                            let code = content[code_start_abs..code_end_abs].trim().to_string();
-                            
-                            let cell = Cell::new(cell_id.clone(), code, dependencies);
-                            self.cells.insert(cell_id, cell);
-                        }
-                    }
+        let mut diagnostics: Vec<Diagnostic> = parse_errors
+            .iter()
+            .map(|e| Diagnostic::new(char_span_to_byte_span(&offsets, e.span()), e.to_string()))
+            .collect();
+
+        let known_ids: HashSet<&str> = cells.iter().map(|c| c.id.as_str()).collect();
+
+        for parsed in &cells {
+            if let Some(existing) = self.cells.get(&parsed.id) {
+                diagnostics.push(
+                    Diagnostic::new(parsed.span.clone(), format!("duplicate cell id `{}`", parsed.id))
+                        .with_secondary(existing.span.clone(), "previously defined here"),
+                );
+                continue;
+            }
+            for dep in &parsed.dependencies {
+                if !known_ids.contains(dep.as_str()) {
+                    diagnostics.push(Diagnostic::new(
+                        parsed.span.clone(),
+                        format!("`depends:{}` names a cell that isn't defined", dep),
+                    ));
                 }
             }
-            
-            pos += 1;
         }
 
+        if !diagnostics.is_empty() {
+            report_diagnostics(content, &diagnostics);
+            return Err(LitProError::ParseError(format!(
+                "{} diagnostic(s) found; see above",
+                diagnostics.len()
+            )));
+        }
+
+        for parsed in cells {
+            let cell = Cell::new(
+                parsed.id.clone(),
+                parsed.code,
+                parsed.dependencies,
+                parsed.lang,
+                parsed.span,
+                parsed.references,
+            );
+            self.cells.insert(parsed.id, cell);
+        }
+
+        self.weave_document_order(content);
+
         Ok(())
     }
 
+    /// Reconstructs the document's reading order from each cell's span, slicing the
+    /// prose that falls between them so `html_litpro` can weave it back in place.
+    fn weave_document_order(&mut self, content: &str) {
+        let mut spans: Vec<(Range<usize>, String)> = self
+            .cells
+            .values()
+            .map(|cell| (cell.span.clone(), cell.id.clone()))
+            .collect();
+        spans.sort_by_key(|(span, _)| span.start);
+
+        let mut blocks = Vec::new();
+        let mut cursor = 0;
+        for (span, id) in spans {
+            if span.start > cursor {
+                blocks.push(DocBlock::Prose(content[cursor..span.start].to_string()));
+            }
+            blocks.push(DocBlock::Cell(id));
+            cursor = span.end;
+        }
+        if cursor < content.len() {
+            blocks.push(DocBlock::Prose(content[cursor..].to_string()));
+        }
+
+        self.document_order = blocks;
+    }
+
     /// Resolve execution order using topological sort
     pub fn resolve_dependencies(&self) -> Result<Vec<String>, LitProError> {
+        self.topo_sort_by(|cell| &cell.dependencies, "Dependency")
+    }
+
+    /// Topologically sorts cells by an arbitrary edge relation (`dependencies` for
+    /// execution order, `references` for chunk-tangling), via Kahn's algorithm.
+    /// Shared so both relations get the same cycle detection for free.
+    fn topo_sort_by<F>(&self, edges_of: F, edge_label: &str) -> Result<Vec<String>, LitProError>
+    where
+        F: Fn(&Cell) -> &Vec<String>,
+    {
         let mut graph: HashMap<String, Vec<String>> = HashMap::new();
         let mut in_degree: HashMap<String, usize> = HashMap::new();
 
         // Initialize graph and in-degree
         for cell_id in self.cells.keys() {
-            graph.entry(cell_id.clone()).or_insert_with(Vec::new);
+            graph.entry(cell_id.clone()).or_default();
             in_degree.insert(cell_id.clone(), 0);
         }
 
         // Build graph and calculate in-degrees
         for cell in self.cells.values() {
-            for dep in &cell.dependencies {
-                if self.cells.contains_key(dep) {
-                    graph.entry(dep.clone()).or_insert_with(Vec::new).push(cell.id.clone());
+            for edge in edges_of(cell) {
+                if self.cells.contains_key(edge) {
+                    graph.entry(edge.clone()).or_default().push(cell.id.clone());
                     *in_degree.get_mut(&cell.id).unwrap() += 1;
                 } else {
-                    eprintln!("Warning: Dependency '{}' not found for cell '{}'", dep, cell.id);
+                    eprintln!("Warning: {} '{}' not found for cell '{}'", edge_label, edge, cell.id);
                 }
             }
         }
@@ -165,8 +469,65 @@ impl LitPro {
         Ok(result)
     }
 
-    /// Execute a literate programming file
-    pub fn run_litpro(&mut self, content: &str) -> Result<(), LitProError> {
+    /// Textually expands every `<<name>>` reference in every cell, recursively, and
+    /// returns each cell's fully-tangled code keyed by id. Reference cycles are
+    /// caught up front by running the same Kahn's-algorithm cycle check used for
+    /// `dependencies`, just over the `references` edges instead.
+    fn tangle_all(&self) -> Result<HashMap<String, String>, LitProError> {
+        self.topo_sort_by(|cell| &cell.references, "Reference")?;
+
+        let mut tangled: HashMap<String, String> = HashMap::new();
+        for cell_id in self.cells.keys() {
+            self.tangle(cell_id, &mut tangled);
+        }
+        Ok(tangled)
+    }
+
+    /// Expands `<<name>>` references in `cell_id`'s code with the referenced cell's
+    /// own (recursively expanded) code, memoizing into `tangled` as it goes.
+    fn tangle(&self, cell_id: &str, tangled: &mut HashMap<String, String>) -> String {
+        if let Some(code) = tangled.get(cell_id) {
+            return code.clone();
+        }
+
+        let cell = &self.cells[cell_id];
+        let mut code = cell.code.clone();
+        for reference in &cell.references {
+            if self.cells.contains_key(reference) {
+                let expansion = self.tangle(reference, tangled);
+                code = code.replace(&format!("<<{}>>", reference), &expansion);
+            }
+        }
+
+        tangled.insert(cell_id.to_string(), code.clone());
+        code
+    }
+
+    /// Cells needed to compile `seeds`: the seeds themselves plus every dependency
+    /// reachable from them, transitively, since the assembled program is one flat
+    /// `fn main()` scope and a seed's code may reference any ancestor's binding.
+    fn dependency_closure(&self, seeds: &HashSet<String>) -> HashSet<String> {
+        let mut needed = seeds.clone();
+        let mut stack: Vec<String> = seeds.iter().cloned().collect();
+        while let Some(cell_id) = stack.pop() {
+            for dep in &self.cells[&cell_id].dependencies {
+                if needed.insert(dep.clone()) {
+                    stack.push(dep.clone());
+                }
+            }
+        }
+        needed
+    }
+
+    /// Execute a literate programming file.
+    ///
+    /// Cells are skipped when their content hash (folded together with the hashes
+    /// of their direct dependencies, so a changed dependency transitively dirties
+    /// everything downstream) matches the sidecar cache from the last run. Only
+    /// stale cells and the dependencies they need to compile are actually
+    /// assembled and recompiled; an unrelated cell with no stale dependent costs
+    /// nothing. Pass `force` to ignore the cache and recompile everything.
+    pub fn run_litpro(&mut self, content: &str, force: bool) -> Result<(), LitProError> {
         println!("Executing literate file...");
 
         // Parse cells
@@ -190,49 +551,220 @@ impl LitPro {
         }
         println!();
 
-        // Execute cells in order
-        for cell_id in execution_order {
-            let cell = self.cells.get_mut(&cell_id).unwrap();
-            
-            println!("\n--- Executing cell: {} ---", cell.id);
+        // Expand <<name>> chunk references before hashing/compiling, since those
+        // are what actually gets executed.
+        let tangled = self.tangle_all()?;
+
+        let mut cache = LitProCache::load();
+        let mut hashes: HashMap<String, String> = HashMap::new();
+        let mut stale: HashSet<String> = HashSet::new();
+
+        for cell_id in &execution_order {
+            let hash = self.cell_hash(cell_id, &tangled, &hashes);
+            let cached_hit = !force
+                && cache
+                    .cells
+                    .get(cell_id)
+                    .map(|entry| entry.hash == hash)
+                    .unwrap_or(false);
+            if !cached_hit {
+                stale.insert(cell_id.clone());
+            }
+            hashes.insert(cell_id.clone(), hash);
+        }
+
+        for cell_id in &execution_order {
+            let cell = &self.cells[cell_id];
+            println!("\n--- Cell: {} ---", cell.id);
             if !cell.dependencies.is_empty() {
                 println!("Dependencies: {}", cell.dependencies.join(", "));
             }
-            
-            // In a real implementation, you would execute the Rust code
-            // For now, we'll just print the code
-            println!("Code:\n{}", cell.code);
-            
-            // Mark as executed
-            cell.executed = true;
-            println!("✓ Cell executed successfully");
+            if stale.contains(cell_id) {
+                println!("Code:\n{}", tangled[cell_id]);
+            } else {
+                println!("(cached) output:\n{}", cache.cells[cell_id].output);
+            }
+        }
+
+        if stale.is_empty() {
+            println!("\n--- All cells up to date; nothing to compile ---");
+        } else {
+            let needed = self.dependency_closure(&stale);
+            let relevant_order: Vec<String> = execution_order
+                .iter()
+                .filter(|cell_id| needed.contains(*cell_id))
+                .cloned()
+                .collect();
+            let outputs = self.compile_and_run(&relevant_order, &tangled)?;
+            for cell_id in &stale {
+                cache.cells.insert(
+                    cell_id.clone(),
+                    CellCacheEntry {
+                        hash: hashes[cell_id].clone(),
+                        output: outputs.get(cell_id).cloned().unwrap_or_default(),
+                    },
+                );
+            }
+            cache.save()?;
+        }
+
+        for cell_id in &execution_order {
+            self.cells.get_mut(cell_id).unwrap().executed = true;
         }
 
         println!("\n--- Execution completed ---");
         Ok(())
     }
 
+    /// Deletes the sidecar cache file, forcing every cell to re-run next time.
+    pub fn clear_cache(&self) -> Result<(), LitProError> {
+        match std::fs::remove_file(CACHE_FILE) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(LitProError::IoError(e)),
+        }
+    }
+
+    /// Hash of a cell's tangled code folded with its sorted dependency hashes, so
+    /// the key transitively covers everything it depends on.
+    fn cell_hash(&self, cell_id: &str, tangled: &HashMap<String, String>, hashes: &HashMap<String, String>) -> String {
+        let cell = &self.cells[cell_id];
+        let mut dep_hashes: Vec<&str> = cell
+            .dependencies
+            .iter()
+            .map(|dep| hashes[dep].as_str())
+            .collect();
+        dep_hashes.sort();
+
+        let mut hasher = Sha256::new();
+        hasher.update(tangled[cell_id].as_bytes());
+        for dep_hash in dep_hashes {
+            hasher.update(dep_hash.as_bytes());
+        }
+        format!("{:x}", hasher.finalize())
+    }
+
+    /// Assembles the tangled cells into one `fn main()` scope, compiles it with
+    /// `rustc`, and returns each cell's captured output, split out via a sentinel
+    /// marker printed after its code.
+    fn compile_and_run(
+        &self,
+        execution_order: &[String],
+        tangled: &HashMap<String, String>,
+    ) -> Result<HashMap<String, String>, LitProError> {
+        let mut source = String::from("fn main() {\n");
+        let mut cell_line_starts: Vec<(usize, String)> = Vec::new();
+        let mut line = 2; // line 1 is `fn main() {`
+
+        for cell_id in execution_order {
+            cell_line_starts.push((line, cell_id.clone()));
+            for code_line in tangled[cell_id].lines() {
+                source.push_str("    ");
+                source.push_str(code_line);
+                source.push('\n');
+                line += 1;
+            }
+            source.push_str(&format!("    println!(\"@@litpro-cell:{}@@\");\n", cell_id));
+            line += 1;
+        }
+        source.push_str("}\n");
+
+        let dir = std::env::temp_dir();
+        let src_path = dir.join(format!("litpro_{}.rs", std::process::id()));
+        let bin_path = dir.join(format!("litpro_{}", std::process::id()));
+        std::fs::write(&src_path, &source).map_err(LitProError::IoError)?;
+
+        let compile = std::process::Command::new("rustc")
+            .arg(&src_path)
+            .arg("-o")
+            .arg(&bin_path)
+            .output()
+            .map_err(LitProError::IoError)?;
+
+        if !compile.status.success() {
+            let stderr = String::from_utf8_lossy(&compile.stderr).to_string();
+            let _ = std::fs::remove_file(&src_path);
+            let cell = Self::cell_for_compiler_line(&stderr, &cell_line_starts)
+                .unwrap_or_else(|| "unknown".to_string());
+            return Err(LitProError::CompileError { cell, stderr });
+        }
+
+        let run = std::process::Command::new(&bin_path)
+            .output()
+            .map_err(LitProError::IoError)?;
+        io::stderr().write_all(&run.stderr).map_err(LitProError::IoError)?;
+
+        let _ = std::fs::remove_file(&src_path);
+        let _ = std::fs::remove_file(&bin_path);
+
+        Ok(Self::split_cell_outputs(&run.stdout))
+    }
+
+    /// Splits a run's combined stdout back into per-cell chunks using the
+    /// `@@litpro-cell:ID@@` sentinel each cell's code is followed by, echoing
+    /// everything to the real stdout as it's consumed.
+    fn split_cell_outputs(stdout: &[u8]) -> HashMap<String, String> {
+        let stdout = String::from_utf8_lossy(stdout);
+        let mut outputs = HashMap::new();
+        let mut buffer = String::new();
+
+        for line in stdout.lines() {
+            if let Some(id) = line
+                .strip_prefix("@@litpro-cell:")
+                .and_then(|s| s.strip_suffix("@@"))
+            {
+                outputs.insert(id.to_string(), buffer.trim_end().to_string());
+                buffer.clear();
+            } else {
+                println!("{}", line);
+                buffer.push_str(line);
+                buffer.push('\n');
+            }
+        }
+
+        outputs
+    }
+
+    /// Maps a `rustc` diagnostic line like `/tmp/litpro_123.rs:12:5: error: ...`
+    /// back to the cell whose generated lines span that line number.
+    fn cell_for_compiler_line(stderr: &str, cell_line_starts: &[(usize, String)]) -> Option<String> {
+        let reported_line = stderr.lines().find_map(|l| {
+            let after = l.split(".rs:").nth(1)?;
+            let digits: String = after.chars().take_while(|c| c.is_ascii_digit()).collect();
+            digits.parse::<usize>().ok()
+        })?;
+
+        cell_line_starts
+            .iter()
+            .rev()
+            .find(|(start, _)| *start <= reported_line)
+            .map(|(_, id)| id.clone())
+    }
+
     /// Export to plain Rust code
     pub fn export_litpro(&self, output_file: &str) -> Result<(), LitProError> {
+        let tangled = self.tangle_all()?;
         let mut file = std::fs::File::create(output_file).map_err(LitProError::IoError)?;
-        
+
         writeln!(file, "// Exported from LitPro").map_err(LitProError::IoError)?;
         writeln!(file).map_err(LitProError::IoError)?;
-        
-        for (id, cell) in &self.cells {
+
+        for id in self.cells.keys() {
             writeln!(file, "// Cell: {}", id).map_err(LitProError::IoError)?;
-            writeln!(file, "{}", cell.code).map_err(LitProError::IoError)?;
+            writeln!(file, "{}", tangled[id]).map_err(LitProError::IoError)?;
             writeln!(file).map_err(LitProError::IoError)?;
         }
-        
+
         println!("Exported to: {}", output_file);
         Ok(())
     }
 
-    /// Generate HTML documentation
+    /// Generate HTML documentation by weaving the original Markdown narrative
+    /// together with the rendered cells, in reading order, the way rustdoc
+    /// weaves prose and code in a guide chapter.
     pub fn html_litpro(&self, output_file: &str) -> Result<(), LitProError> {
         let mut file = std::fs::File::create(output_file).map_err(LitProError::IoError)?;
-        
+
         write!(file, r#"<!DOCTYPE html>
 <html>
 <head>
@@ -241,29 +773,63 @@ impl LitPro {
         body {{ font-family: Arial, sans-serif; margin: 40px; }}
         .cell {{ margin: 20px 0; padding: 15px; border-left: 3px solid #007acc; }}
         .code {{ background: #f4f4f4; padding: 10px; border-radius: 4px; }}
+        .depends {{ font-size: 0.9em; color: #555; }}
+        .depends a {{ color: #007acc; }}
         pre {{ margin: 0; }}
+        .hl-keyword {{ color: #c2185b; font-weight: bold; }}
+        .hl-string {{ color: #2e7d32; }}
+        .hl-comment {{ color: #757575; font-style: italic; }}
+        .hl-function {{ color: #1565c0; }}
+        .hl-type {{ color: #6a1b9a; }}
     </style>
 </head>
 <body>
     <h1>LitPro Documentation</h1>"#).map_err(LitProError::IoError)?;
-        
-        for (id, cell) in &self.cells {
-            write!(file, r#"
-    <div class="cell">
-        <h3>Cell: {}</h3>
+
+        for block in &self.document_order {
+            match block {
+                DocBlock::Prose(text) => {
+                    if text.trim().is_empty() {
+                        continue;
+                    }
+                    let mut rendered = String::new();
+                    md_html::push_html(&mut rendered, MarkdownParser::new(text));
+                    write!(file, "\n{}", rendered).map_err(LitProError::IoError)?;
+                }
+                DocBlock::Cell(id) => {
+                    let cell = &self.cells[id];
+                    let depends_on = if cell.dependencies.is_empty() {
+                        String::new()
+                    } else {
+                        let links = cell
+                            .dependencies
+                            .iter()
+                            .map(|dep| format!(r##"<a href="#cell-{0}">{0}</a>"##, dep))
+                            .collect::<Vec<_>>()
+                            .join(", ");
+                        format!(r#"<p class="depends">depends on: {}</p>"#, links)
+                    };
+
+                    write!(file, r#"
+    <div class="cell" id="cell-{0}">
+        <h3>Cell: {0}</h3>
+        {1}
         <div class="code">
-            <pre><code class="language-rust">{}</code></pre>
+            <pre><code class="language-rust">{2}</code></pre>
         </div>
-    </div>"#, 
-            id, 
-            self.escape_html(&cell.code)
-        ).map_err(LitProError::IoError)?;
+    </div>"#,
+                        id,
+                        depends_on,
+                        self.highlight_rust(&cell.code)
+                    ).map_err(LitProError::IoError)?;
+                }
+            }
         }
-        
+
         write!(file, r#"
 </body>
 </html>"#).map_err(LitProError::IoError)?;
-        
+
         println!("HTML documentation generated: {}", output_file);
         Ok(())
     }
@@ -276,6 +842,56 @@ impl LitPro {
          .replace("\"", "&quot;")
          .replace("'", "&#x27;")
     }
+
+    /// Syntax-highlights Rust code with `tree-sitter`, wrapping each captured node
+    /// in a `<span class="hl-*">` and leaving everything else escaped plain text.
+    /// Falls back to plain escaped code if the grammar or query fails to load.
+    fn highlight_rust(&self, code: &str) -> String {
+        let mut parser = TsParser::new();
+        let Ok(()) = parser.set_language(tree_sitter_rust::language()) else {
+            return self.escape_html(code);
+        };
+        let Some(tree) = parser.parse(code, None) else {
+            return self.escape_html(code);
+        };
+        let Ok(query) = Query::new(tree_sitter_rust::language(), RUST_HIGHLIGHT_QUERY) else {
+            return self.escape_html(code);
+        };
+
+        let mut cursor = QueryCursor::new();
+        let mut spans: Vec<(usize, usize, &str)> = Vec::new();
+        for m in cursor.matches(&query, tree.root_node(), code.as_bytes()) {
+            for capture in m.captures {
+                let name = query.capture_names()[capture.index as usize].as_str();
+                spans.push((capture.node.start_byte(), capture.node.end_byte(), name));
+            }
+        }
+        // Widest (outermost) span first so nested captures at the same start don't
+        // get emitted twice; narrower duplicates are skipped by the `pos` cursor below.
+        spans.sort_by_key(|(start, end, _)| (*start, std::cmp::Reverse(*end)));
+
+        let mut output = String::new();
+        let mut pos = 0;
+        for (start, end, name) in spans {
+            if start < pos {
+                continue;
+            }
+            if start > pos {
+                output.push_str(&self.escape_html(&code[pos..start]));
+            }
+            output.push_str(&format!(
+                r#"<span class="hl-{}">{}</span>"#,
+                name,
+                self.escape_html(&code[start..end])
+            ));
+            pos = end;
+        }
+        if pos < code.len() {
+            output.push_str(&self.escape_html(&code[pos..]));
+        }
+
+        output
+    }
 }
 
 // Example usage
@@ -289,20 +905,20 @@ let x = 10;
 let y = 20;
 ```
 
-<!-- cell:compute depends:setup -->
+<!-- cell:compute (depends:setup) -->
 ```rust
 let result = x + y;
 println!("Sum: {}", result);
 ```
 
-<!-- cell:display depends:compute -->
+<!-- cell:display (depends:compute) -->
 ```rust
 println!("The final result is: {}", result);
 ```
 "#;
 
     let mut litpro = LitPro::new();
-    litpro.run_litpro(content)?;
+    litpro.run_litpro(content, false)?;
     
     Ok(())
 }
@@ -324,4 +940,173 @@ let x = 5;
         assert_eq!(litpro.cells.len(), 1);
         assert!(litpro.cells.contains_key("test_id"));
     }
+
+    #[test]
+    fn test_spans_are_byte_indexed_with_multibyte_prose() {
+        // Prose ahead of the cell is full of multi-byte UTF-8 (café, em dash,
+        // ellipsis): char and byte offsets diverge here, so a char-indexed span
+        // would either slice mid-character (panicking) or land on the wrong text.
+        let content = "# Café Notes\n\nSome prose with an em dash — and ellipsis… here.\n\n<!-- cell:setup -->\n```rust\nlet x = 1;\n```\n";
+
+        let mut litpro = LitPro::new();
+        assert!(litpro.parse_cells(content).is_ok());
+
+        let cell = &litpro.cells["setup"];
+        assert_eq!(&content[cell.span.clone()], "<!-- cell:setup -->\n```rust\nlet x = 1;\n```");
+    }
+
+    #[test]
+    fn test_weave_document_order_with_multibyte_and_trailing_prose() {
+        // Same multi-byte prose as the span test, but this exercises
+        // weave_document_order's own slicing: leading prose before the cell, and
+        // trailing prose after it, both need to come back whole and un-truncated.
+        let content = "# Café Notes\n\nSome prose with an em dash — and ellipsis… here.\n\n<!-- cell:setup -->\n```rust\nlet x = 1;\n```\n\nTrailing café notes.\n";
+
+        let mut litpro = LitPro::new();
+        assert!(litpro.parse_cells(content).is_ok());
+
+        let prose_blocks: Vec<&str> = litpro
+            .document_order
+            .iter()
+            .filter_map(|block| match block {
+                DocBlock::Prose(text) => Some(text.as_str()),
+                DocBlock::Cell(_) => None,
+            })
+            .collect();
+
+        assert!(prose_blocks[0].ends_with("em dash — and ellipsis… here.\n\n"));
+        assert_eq!(prose_blocks[1], "\n\nTrailing café notes.\n");
+    }
+
+    #[test]
+    fn test_duplicate_id_diagnostic_with_multibyte_prose_does_not_panic() {
+        // report_diagnostics hands the duplicate-id span to ariadne against
+        // Source::from(content); with multi-byte prose ahead of the marker, a
+        // char-indexed span would land on the wrong text or panic on a
+        // non-char-boundary index. A clean Err (no panic) proves the span
+        // ariadne receives is a valid byte range.
+        let mut litpro = LitPro::new();
+        litpro
+            .parse_cells("<!-- cell:a -->\n```rust\nlet x = 1;\n```\n")
+            .unwrap();
+
+        let content = "Café — notes…\n\n<!-- cell:a -->\n```rust\nlet y = 2;\n```\n";
+        assert!(matches!(
+            litpro.parse_cells(content),
+            Err(LitProError::ParseError(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_multiple_cells_with_dependencies() {
+        let content = r#"# Doc
+
+<!-- cell:setup -->
+```rust
+let x = 1;
+```
+
+<!-- cell:compute (depends:setup) -->
+```rust
+let y = x;
+```
+"#;
+
+        let mut litpro = LitPro::new();
+        assert!(litpro.parse_cells(content).is_ok());
+        assert_eq!(litpro.cells.len(), 2);
+        assert_eq!(litpro.cells["compute"].dependencies, vec!["setup".to_string()]);
+    }
+
+    #[test]
+    fn test_document_recovers_past_malformed_marker() {
+        // The first marker is missing its closing `-->`, so it can never parse as
+        // a cell; the grammar should skip past it and still pick up `good`.
+        let content = "<!-- cell:broken\n```rust\nlet x = 1;\n```\n\n<!-- cell:good -->\n```rust\nlet y = 2;\n```\n";
+
+        let (cells, errors) = document().parse_recovery(content);
+        assert!(!errors.is_empty());
+        let ids: Vec<String> = cells
+            .unwrap_or_default()
+            .into_iter()
+            .map(|c| c.id)
+            .collect();
+        assert_eq!(ids, vec!["good".to_string()]);
+    }
+
+    #[test]
+    fn test_changed_dependency_invalidates_transitive_hash() {
+        fn hash_of(litpro: &LitPro, order: &[String], target: &str) -> String {
+            let tangled = litpro.tangle_all().unwrap();
+            let mut hashes = HashMap::new();
+            for id in order {
+                let hash = litpro.cell_hash(id, &tangled, &hashes);
+                hashes.insert(id.clone(), hash);
+            }
+            hashes[target].clone()
+        }
+
+        let doc_a = "<!-- cell:setup -->\n```rust\nlet x = 1;\n```\n\n<!-- cell:compute (depends:setup) -->\n```rust\nlet y = x;\n```\n";
+        let doc_b = "<!-- cell:setup -->\n```rust\nlet x = 2;\n```\n\n<!-- cell:compute (depends:setup) -->\n```rust\nlet y = x;\n```\n";
+
+        let mut a = LitPro::new();
+        a.parse_cells(doc_a).unwrap();
+        let order_a = a.resolve_dependencies().unwrap();
+
+        let mut b = LitPro::new();
+        b.parse_cells(doc_b).unwrap();
+        let order_b = b.resolve_dependencies().unwrap();
+
+        // `compute`'s own code is identical in both documents; only its
+        // dependency `setup` changed. Its hash must still differ.
+        assert_ne!(
+            hash_of(&a, &order_a, "compute"),
+            hash_of(&b, &order_b, "compute")
+        );
+    }
+
+    #[test]
+    fn test_reference_cycle_is_detected() {
+        let content = "<!-- cell:a -->\n```rust\n<<b>>\n```\n\n<!-- cell:b -->\n```rust\n<<a>>\n```\n";
+
+        let mut litpro = LitPro::new();
+        litpro.parse_cells(content).unwrap();
+
+        assert!(matches!(
+            litpro.tangle_all(),
+            Err(LitProError::CircularDependencyError)
+        ));
+    }
+
+    #[test]
+    fn test_dependency_closure_excludes_unrelated_cells() {
+        let content = "<!-- cell:setup -->\n```rust\nlet x = 1;\n```\n\n<!-- cell:compute (depends:setup) -->\n```rust\nlet y = x;\n```\n\n<!-- cell:unrelated -->\n```rust\nlet z = 2;\n```\n";
+
+        let mut litpro = LitPro::new();
+        litpro.parse_cells(content).unwrap();
+
+        let mut stale = HashSet::new();
+        stale.insert("compute".to_string());
+        let needed = litpro.dependency_closure(&stale);
+
+        // `compute` needs `setup`'s binding to compile, but `unrelated` shares no
+        // dependency edge with it and must not be dragged into the rebuild.
+        assert!(needed.contains("compute"));
+        assert!(needed.contains("setup"));
+        assert!(!needed.contains("unrelated"));
+    }
+
+    #[test]
+    fn test_compile_and_run_attributes_output_per_cell() {
+        let content = "<!-- cell:setup -->\n```rust\nlet x = 1;\n```\n\n<!-- cell:compute (depends:setup) -->\n```rust\nlet y = x + 1;\nprintln!(\"{}\", y);\n```\n";
+
+        let mut litpro = LitPro::new();
+        litpro.parse_cells(content).unwrap();
+        let order = litpro.resolve_dependencies().unwrap();
+        let tangled = litpro.tangle_all().unwrap();
+
+        let outputs = litpro.compile_and_run(&order, &tangled).unwrap();
+        assert_eq!(outputs["setup"], "");
+        assert_eq!(outputs["compute"], "2");
+    }
 }
\ No newline at end of file